@@ -1,17 +1,166 @@
+use crate::decoders::DecoderRegistry;
 use crate::error::{AppError, Result};
+use crate::layout::{self, LayoutOptions, PageLayout};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
 use image::{DynamicImage, GenericImageView, ImageOutputFormat};
 use log::{debug, info, warn};
 use pdf_writer::{Pdf, Ref, Name, Rect, Filter, Finish};
-use std::collections::BTreeMap;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
 use std::fs;
-use std::io::Cursor;
+use std::io::{Cursor, Write};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+/// 画像の埋め込み方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EmbedMode {
+    /// 元画像の形式に応じて自動判定する（既定値）
+    Auto,
+    /// 常にJPEGへ再エンコードする（従来の挙動）
+    Lossy,
+    /// 可能な限り再エンコードを避け、ロスレスに埋め込む
+    Lossless,
+}
+
+impl Default for EmbedMode {
+    fn default() -> Self {
+        EmbedMode::Auto
+    }
+}
+
+/// 変換処理の挙動を制御するオプション
+#[derive(Debug, Clone)]
+pub struct ConverterOptions {
+    /// 画像の埋め込み方式
+    pub embed_mode: EmbedMode,
+    /// SVGをラスタライズする際の解像度（DPI）
+    pub svg_dpi: f32,
+    /// SVGをラスタライズする際の倍率。指定時は`svg_dpi`より優先される
+    pub svg_scale: Option<f32>,
+    /// ページレイアウト設定
+    pub layout: LayoutOptions,
+    /// 破損ファイルなどをスキップせず、最初のエラーで即座に失敗させる
+    pub strict: bool,
+    /// スキップした画像のレポートを書き出すサイドカーファイル
+    pub error_log: Option<PathBuf>,
+    /// サブディレクトリも再帰的に検索する
+    pub recursive: bool,
+    /// デコード/エンコードを並列実行する際の最大並列数。`None`の場合はrayonの既定値を使う
+    pub jobs: Option<usize>,
+}
+
+impl Default for ConverterOptions {
+    fn default() -> Self {
+        Self {
+            embed_mode: EmbedMode::default(),
+            svg_dpi: 150.0,
+            svg_scale: None,
+            layout: LayoutOptions::default(),
+            strict: false,
+            error_log: None,
+            recursive: false,
+            jobs: None,
+        }
+    }
+}
+
+/// 1ページ分のデコード/エンコード結果。並列ステージの出力で、元の並び順を保つための
+/// `file_index`（元画像ファイルの順序）と`frame_index`（同一ファイル内のフレーム順序）を持つ
+struct PreparedPage {
+    file_index: usize,
+    frame_index: usize,
+    width: u32,
+    height: u32,
+    layout: PageLayout,
+    encoded: EncodedImage,
+}
+
+/// 1ファイル分の並列処理結果
+struct FileOutcome {
+    pages: Vec<PreparedPage>,
+    skipped: Vec<SkippedImage>,
+}
+
+/// バッチ処理中にスキップされた画像の情報
+#[derive(Debug, Clone)]
+struct SkippedImage {
+    path: PathBuf,
+    reason: String,
+}
+
+/// 自然順ソート用のトークン。数字の連続は数値として、それ以外は文字列として比較する
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NaturalToken {
+    Text(String),
+    Number(u64),
+}
+
+impl PartialOrd for NaturalToken {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NaturalToken {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (NaturalToken::Number(a), NaturalToken::Number(b)) => a.cmp(b),
+            (NaturalToken::Text(a), NaturalToken::Text(b)) => a.cmp(b),
+            // 同じ位置のトークン種別が一致しない場合は、数値を文字列化して辞書順に比較する
+            (NaturalToken::Number(a), NaturalToken::Text(b)) => a.to_string().cmp(b),
+            (NaturalToken::Text(a), NaturalToken::Number(b)) => a.cmp(&b.to_string()),
+        }
+    }
+}
+
+/// ページに埋め込む画像サンプルデータの色空間
+enum FlateColorSpace {
+    /// 1チャンネルのグレースケール
+    Gray,
+    /// 3チャンネルのRGB
+    Rgb,
+}
+
+/// JPEGバイト列のSOFセグメントから判明する色空間
+enum JpegColorSpace {
+    /// 1コンポーネント（グレースケールJPEG）
+    Gray,
+    /// 3コンポーネント（YCbCr/RGB）
+    Rgb,
+    /// 4コンポーネント（CMYK/YCCK、多くはAdobe反転規約）
+    Cmyk,
+}
+
+/// PDFに書き込む画像データの表現
+enum ImagePayload {
+    /// 既存のJPEGバイト列をそのまま埋め込む（再エンコードなし）
+    Jpeg {
+        data: Vec<u8>,
+        color_space: JpegColorSpace,
+    },
+    /// 生のサンプルデータをzlib圧縮し、FlateDecodeで埋め込む
+    Flate {
+        data: Vec<u8>,
+        color_space: FlateColorSpace,
+        bits_per_component: i32,
+    },
+}
+
+/// ページ埋め込み用にエンコードされた画像
+struct EncodedImage {
+    payload: ImagePayload,
+    /// 透明度を持つ場合の、zlib圧縮済み8bitグレースケールのアルファチャンネル
+    smask: Option<Vec<u8>>,
+}
+
 /// 画像ファイルをPDFに変換するコンバーター
 pub struct ImageToPdfConverter {
-    /// サポートされている画像拡張子
-    supported_extensions: Vec<&'static str>,
+    /// 拡張子ごとのデコーダーレジストリ
+    decoders: DecoderRegistry,
+    /// 変換オプション
+    options: ConverterOptions,
 }
 
 impl ImageToPdfConverter {
@@ -20,8 +169,24 @@ impl ImageToPdfConverter {
     /// # Returns
     /// * `Self` - コンバーターインスタンス
     pub fn new() -> Self {
+        Self::with_options(ConverterOptions::default())
+    }
+
+    /// オプションを指定してコンバーターインスタンスを作成する
+    ///
+    /// # Arguments
+    /// * `options` - 変換オプション
+    ///
+    /// # Returns
+    /// * `Self` - コンバーターインスタンス
+    pub fn with_options(options: ConverterOptions) -> Self {
+        let svg_options = crate::decoders::SvgOptions {
+            dpi: options.svg_dpi,
+            scale: options.svg_scale,
+        };
         Self {
-            supported_extensions: vec!["jpg", "jpeg", "png", "webp"],
+            decoders: DecoderRegistry::new(svg_options),
+            options,
         }
     }
 
@@ -35,38 +200,70 @@ impl ImageToPdfConverter {
     /// * `Result<()>` - 変換の成功/失敗
     pub fn convert(&self, input_dir: &Path, output_file: &Path) -> Result<()> {
         info!("Starting image to PDF conversion");
-        
+
         // 画像ファイルを収集してソート
         let image_files = self.collect_and_sort_images(input_dir)?;
-        
+
         if image_files.is_empty() {
             return Err(AppError::NoImagesFound(input_dir.to_string_lossy().to_string()));
         }
 
         info!("Found {} image files", image_files.len());
 
-        // PDFを作成
+        // デコードとエンコードは並列実行し、ページごとのバイト列を元の並び順を保ったまま集める。
+        // `pdf_writer`の`Pdf`はビルド中スレッド間で共有できないため、ref割り当てとオブジェクト書き込みは
+        // このあとの単一スレッドの最終パスでのみ行う
+        let pool = match self.options.jobs {
+            Some(jobs) => Some(
+                ThreadPoolBuilder::new()
+                    .num_threads(jobs)
+                    .build()
+                    .map_err(|e| AppError::PdfCreation(format!("Failed to build thread pool: {}", e)))?,
+            ),
+            None => None,
+        };
+
+        let prepare_all = || -> Vec<Result<FileOutcome>> {
+            image_files
+                .par_iter()
+                .enumerate()
+                .map(|(file_index, file_path)| self.prepare_file(file_index, file_path))
+                .collect()
+        };
+        let file_results = match &pool {
+            Some(pool) => pool.install(prepare_all),
+            None => prepare_all(),
+        };
+
+        let mut prepared_pages = Vec::new();
+        let mut skipped = Vec::new();
+        for result in file_results {
+            let outcome = result?;
+            prepared_pages.extend(outcome.pages);
+            skipped.extend(outcome.skipped);
+        }
+
+        self.report_skipped_images(&skipped)?;
+
+        if prepared_pages.is_empty() {
+            return Err(AppError::NoPagesProduced(input_dir.to_string_lossy().to_string()));
+        }
+
+        // PDFを作成（単一スレッドの最終パス：ref割り当てとページツリーの組み立て）
         let mut pdf = Pdf::new();
-        
+
         // カタログとページツリーのID
         let catalog_id = Ref::new(1);
         let page_tree_id = Ref::new(2);
-        
+
         let mut page_ids = Vec::new();
         let mut next_id = 3;
-        
-        // 各画像ファイルを処理
-        for (index, file_path) in image_files.iter().enumerate() {
-            info!("Processing image {}/{}: {:?}", index + 1, image_files.len(), file_path);
-            
-            // 画像を読み込んで処理
-            let processed_image = self.process_image(file_path)?;
-            
-            // 画像をPDFページに追加
+
+        for prepared in &prepared_pages {
             let page_id = Ref::new(next_id);
             next_id += 1;
-            
-            self.add_image_as_page(&mut pdf, page_id, &processed_image, &mut next_id)?;
+
+            self.write_page(&mut pdf, page_id, prepared, &mut next_id)?;
             page_ids.push(page_id);
         }
 
@@ -85,12 +282,166 @@ impl ImageToPdfConverter {
         info!("Saving PDF file: {:?}", output_file);
         let bytes = pdf.finish();
         fs::write(output_file, bytes)?;
-        
+
         info!("PDF saved successfully: {:?}", output_file);
         Ok(())
     }
 
-    /// 指定されたディレクトリから画像ファイルを収集し、ファイル名でソートする
+    /// 1ファイルをデコードし、フレームごとにPDF埋め込み用のエンコードとレイアウト計算まで行う
+    ///
+    /// strictモードでは最初のエラーをそのまま返す。非strictモードでは、破損ファイルや
+    /// 不正なフレームを`FileOutcome::skipped`に記録し、残りのフレームの処理を継続する。
+    /// この関数は並列に（ファイルごとに1タスクとして）呼び出される。
+    ///
+    /// # Arguments
+    /// * `file_index` - `image_files`内での元の並び順（最終的な組み立てで使う）
+    /// * `file_path` - 画像ファイルのパス
+    ///
+    /// # Returns
+    /// * `Result<FileOutcome>` - 準備済みページとスキップ情報
+    fn prepare_file(&self, file_index: usize, file_path: &Path) -> Result<FileOutcome> {
+        let frames = match self.process_image(file_path) {
+            Ok(frames) => frames,
+            Err(e) => {
+                if self.options.strict {
+                    return Err(e);
+                }
+                warn!("Skipping {:?}: {}", file_path, e);
+                return Ok(FileOutcome {
+                    pages: Vec::new(),
+                    skipped: vec![SkippedImage { path: file_path.to_path_buf(), reason: e.to_string() }],
+                });
+            }
+        };
+        let frame_count = frames.len();
+
+        let mut pages = Vec::new();
+        let mut skipped = Vec::new();
+
+        for (frame_index, frame) in frames.iter().enumerate() {
+            if frame_count > 1 {
+                debug!("Expanding frame {}/{} of {:?} into its own page", frame_index + 1, frame_count, file_path);
+            }
+
+            let (width, height) = frame.dimensions();
+            if width == 0 || height == 0 {
+                let reason = format!("zero-dimension image (frame {}/{})", frame_index + 1, frame_count);
+                if self.options.strict {
+                    return Err(AppError::PdfCreation(format!("{:?}: {}", file_path, reason)));
+                }
+                warn!("Skipping {:?}: {}", file_path, reason);
+                skipped.push(SkippedImage { path: file_path.to_path_buf(), reason });
+                continue;
+            }
+
+            match self.encode_image(file_path, frame, frame_count) {
+                Ok(encoded) => {
+                    let layout = layout::compute_layout(width, height, &self.options.layout);
+                    pages.push(PreparedPage { file_index, frame_index, width, height, layout, encoded });
+                }
+                Err(e) => {
+                    if self.options.strict {
+                        return Err(e);
+                    }
+                    warn!("Skipping frame {}/{} of {:?}: {}", frame_index + 1, frame_count, file_path, e);
+                    skipped.push(SkippedImage { path: file_path.to_path_buf(), reason: e.to_string() });
+                }
+            }
+        }
+
+        Ok(FileOutcome { pages, skipped })
+    }
+
+    /// `input_dir`直下の各サブフォルダを、フォルダ名を付けたPDFとして`outdir`に個別出力する
+    ///
+    /// # Arguments
+    /// * `input_dir` - サブフォルダを含む親ディレクトリ
+    /// * `outdir` - PDFの出力先ディレクトリ
+    ///
+    /// # Returns
+    /// * `Result<()>` - 変換の成功/失敗
+    pub fn convert_bulk(&self, input_dir: &Path, outdir: &Path) -> Result<()> {
+        fs::create_dir_all(outdir)?;
+
+        // `report_skipped_images`はサブフォルダごとに呼ばれて同じファイルに追記していくため、
+        // 前回の実行内容を引きずらないようここで一度だけリセットしておく
+        if let Some(error_log_path) = &self.options.error_log {
+            fs::write(error_log_path, "")?;
+        }
+
+        let mut subdirs: Vec<PathBuf> = WalkDir::new(input_dir)
+            .max_depth(1)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.into_path())
+            .filter(|path| path.is_dir() && path != input_dir)
+            .collect();
+        subdirs.sort();
+
+        if subdirs.is_empty() {
+            return Err(AppError::NoImagesFound(input_dir.to_string_lossy().to_string()));
+        }
+
+        for subdir in &subdirs {
+            let folder_name = subdir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("output");
+            let output_file = outdir.join(format!("{}.pdf", folder_name));
+
+            info!("Converting subfolder {:?} -> {:?}", subdir, output_file);
+            match self.convert(subdir, &output_file) {
+                Ok(()) => {}
+                Err(AppError::NoImagesFound(_)) => {
+                    warn!("No images found in {:?}, skipping", subdir);
+                }
+                Err(AppError::NoPagesProduced(_)) => {
+                    warn!("No pages could be produced from any image in {:?}, skipping", subdir);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// スキップした画像のサマリーをログに出力し、必要であればサイドカーファイルに書き出す
+    ///
+    /// `convert_bulk`ではサブフォルダごとにこの関数が呼ばれるため、サイドカーファイルには
+    /// 追記していく（`convert_bulk`側が実行開始時に一度だけファイルをリセットする）。
+    ///
+    /// # Arguments
+    /// * `skipped` - スキップされた画像の一覧
+    ///
+    /// # Returns
+    /// * `Result<()>` - レポート書き出しの成功/失敗
+    fn report_skipped_images(&self, skipped: &[SkippedImage]) -> Result<()> {
+        if skipped.is_empty() {
+            return Ok(());
+        }
+
+        warn!("Skipped {} image(s) during conversion:", skipped.len());
+        let mut report = String::new();
+        for item in skipped {
+            warn!("  {:?}: {}", item.path, item.reason);
+            report.push_str(&format!("{}: {}\n", item.path.display(), item.reason));
+        }
+
+        if let Some(error_log_path) = &self.options.error_log {
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(error_log_path)?;
+            file.write_all(report.as_bytes())?;
+            info!("Wrote error log to {:?}", error_log_path);
+        }
+
+        Ok(())
+    }
+
+    /// 指定されたディレクトリから画像ファイルを収集し、自然順でソートする
+    ///
+    /// `recursive`オプションが有効な場合はサブディレクトリも再帰的に検索する。
     ///
     /// # Arguments
     /// * `dir` - 検索するディレクトリ
@@ -98,13 +449,18 @@ impl ImageToPdfConverter {
     /// # Returns
     /// * `Result<Vec<PathBuf>>` - ソートされた画像ファイルのリスト
     fn collect_and_sort_images(&self, dir: &Path) -> Result<Vec<PathBuf>> {
-        let mut image_files = BTreeMap::new();
+        let mut image_files = Vec::new();
+
+        let walker = if self.options.recursive {
+            WalkDir::new(dir)
+        } else {
+            WalkDir::new(dir).max_depth(1)
+        };
 
-        // ディレクトリを再帰的に検索
-        for entry in WalkDir::new(dir).max_depth(1).into_iter() {
+        for entry in walker.into_iter() {
             let entry = entry?;
             let path = entry.path();
-            
+
             // ファイルかどうかチェック
             if !path.is_file() {
                 continue;
@@ -113,130 +469,218 @@ impl ImageToPdfConverter {
             // 拡張子をチェック
             if let Some(extension) = path.extension() {
                 let ext_str = extension.to_string_lossy().to_lowercase();
-                if self.supported_extensions.contains(&ext_str.as_str()) {
-                    // ファイル名から数値を抽出してソートキーとして使用
-                    if let Some(sort_key) = self.extract_numeric_sort_key(path) {
-                        image_files.insert(sort_key, path.to_path_buf());
-                    } else {
-                        // 数値が抽出できない場合は、ファイル名をそのまま使用
-                        warn!("Could not extract numeric sort key from: {:?}", path);
-                        let file_name = path.file_name()
-                            .and_then(|name| name.to_str())
-                            .unwrap_or("")
-                            .to_string();
-                        image_files.insert(file_name, path.to_path_buf());
-                    }
+                if self.decoders.supported_extensions().contains(&ext_str.as_str()) {
+                    let sort_key = Self::natural_sort_key(path, dir);
+                    image_files.push((sort_key, path.to_path_buf()));
                 }
             }
         }
 
-        let sorted_files: Vec<PathBuf> = image_files.into_values().collect();
+        image_files.sort_by(|(key_a, _), (key_b, _)| key_a.cmp(key_b));
+        let sorted_files: Vec<PathBuf> = image_files.into_iter().map(|(_, path)| path).collect();
         debug!("Collected {} image files", sorted_files.len());
-        
+
         Ok(sorted_files)
     }
 
-    /// ファイル名から数値のソートキーを抽出する
+    /// ファイルパスを自然順ソート用のトークン列に変換する
+    ///
+    /// `base_dir`からの相対パスの各ディレクトリ階層を順番にトークン化するため、
+    /// `--recursive`で複数のサブフォルダを1つのPDFにまとめても、各フォルダの
+    /// ページがバラけず連続した並びになる。各階層内では、数字の連続と非数字の
+    /// 連続に交互に分割し、数字部分は数値として、それ以外は文字列として比較することで、
+    /// `img2 < img10`のような直感通りの順序になるようにする。
     ///
     /// # Arguments
     /// * `path` - ファイルパス
+    /// * `base_dir` - 検索の起点となったディレクトリ
     ///
     /// # Returns
-    /// * `Option<String>` - ソートキー（数値を0パディングした文字列）
-    fn extract_numeric_sort_key(&self, path: &Path) -> Option<String> {
-        let file_stem = path.file_stem()?.to_str()?;
-        
-        // 数値部分を抽出
-        let numeric_part: String = file_stem.chars().filter(|c| c.is_ascii_digit()).collect();
-        
-        if numeric_part.is_empty() {
-            return None;
+    /// * `Vec<NaturalToken>` - 自然順ソート用のキー
+    fn natural_sort_key(path: &Path, base_dir: &Path) -> Vec<NaturalToken> {
+        let relative = path.strip_prefix(base_dir).unwrap_or(path);
+
+        let mut components: Vec<String> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+
+        // 拡張子はソート順に影響させない（従来のfile_stemベースの挙動を踏襲）
+        if let Some(last) = components.last_mut() {
+            if let Some(stem) = Path::new(last.as_str()).file_stem().and_then(|s| s.to_str()) {
+                *last = stem.to_string();
+            }
         }
 
-        // 数値を整数に変換してから、0パディングした文字列に変換
-        if let Ok(num) = numeric_part.parse::<u32>() {
-            Some(format!("{:010}", num)) // 10桁の0パディング
-        } else {
-            None
+        let mut tokens = Vec::new();
+        for (index, component) in components.iter().enumerate() {
+            if index > 0 {
+                // ディレクトリ階層の境界を示し、隣接するテキストトークンとの衝突を避ける
+                tokens.push(NaturalToken::Text("/".to_string()));
+            }
+            tokens.extend(Self::tokenize(component));
+        }
+
+        tokens
+    }
+
+    /// 文字列を数字の連続と非数字の連続に交互に分割し、自然順ソート用のトークンにする
+    fn tokenize(text: &str) -> Vec<NaturalToken> {
+        let mut tokens = Vec::new();
+        let mut chars = text.chars().peekable();
+
+        while let Some(&c) = chars.peek() {
+            if c.is_ascii_digit() {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_ascii_digit() {
+                        number.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(NaturalToken::Number(number.parse().unwrap_or(u64::MAX)));
+            } else {
+                let mut run = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_ascii_digit() {
+                        break;
+                    }
+                    run.push(c);
+                    chars.next();
+                }
+                tokens.push(NaturalToken::Text(run));
+            }
         }
+
+        tokens
     }
 
-    /// 画像ファイルを処理する（WebPの場合はJPEG変換）
+    /// 画像ファイルを処理する
+    ///
+    /// 対応する拡張子のデコーダーにディスパッチする。マルチページTIFFや
+    /// アニメーションGIFのように複数フレームを持つ形式は、フレームごとに
+    /// 1要素を持つベクタを返し、呼び出し側でページごとに展開する。
     ///
     /// # Arguments
     /// * `file_path` - 画像ファイルのパス
     ///
     /// # Returns
-    /// * `Result<DynamicImage>` - 処理済みの画像
-    fn process_image(&self, file_path: &Path) -> Result<DynamicImage> {
-        let img = image::open(file_path)?;
-        
-        // WebPの場合はJPEG形式に変換処理をログ出力
-        if let Some(extension) = file_path.extension() {
-            let ext_str = extension.to_string_lossy().to_lowercase();
-            if ext_str == "webp" {
-                debug!("Processing WebP image (will be converted to JPEG): {:?}", file_path);
-            }
-        }
+    /// * `Result<Vec<DynamicImage>>` - デコードされたフレーム一覧
+    fn process_image(&self, file_path: &Path) -> Result<Vec<DynamicImage>> {
+        let extension = file_path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .ok_or_else(|| AppError::InvalidExtension(file_path.to_string_lossy().to_string()))?;
 
-        Ok(img)
+        self.decoders.decode(file_path, &extension)
     }
 
-    /// 画像をPDFページとして追加する
+    /// 準備済みのページをPDFオブジェクトとして書き込む
+    ///
+    /// デコード/エンコードは`prepare_file`で既に完了しているため、ここでは
+    /// ref番号の割り当てとXObject/コンテンツストリーム/ページオブジェクトの
+    /// 書き込みのみを行う単一スレッドの処理になる。
     ///
     /// # Arguments
     /// * `pdf` - PDFライター
     /// * `page_id` - ページID
-    /// * `img` - 追加する画像
+    /// * `prepared` - 事前にエンコード済みのページデータ
     /// * `next_id` - 次に使用するID（更新される）
     ///
     /// # Returns
     /// * `Result<()>` - 処理の成功/失敗
-    fn add_image_as_page(
-        &self, 
-        pdf: &mut Pdf, 
-        page_id: Ref, 
-        img: &DynamicImage, 
-        next_id: &mut i32
-    ) -> Result<()> {
-        // 画像のサイズを取得
-        let (img_width, img_height) = img.dimensions();
-        
-        // 72 DPIでのページサイズ計算（ポイント単位）
-        let dpi = 72.0_f32;
-        let page_width = img_width as f32 / dpi * 72.0;
-        let page_height = img_height as f32 / dpi * 72.0;
-        
-        // 画像をJPEGバイトデータに変換
-        let image_bytes = self.image_to_jpeg_bytes(img)?;
-        
+    fn write_page(&self, pdf: &mut Pdf, page_id: Ref, prepared: &PreparedPage, next_id: &mut i32) -> Result<()> {
+        let img_width = prepared.width;
+        let img_height = prepared.height;
+        let page_layout = prepared.layout;
+        let encoded = &prepared.encoded;
+
+        debug!(
+            "Writing page for file #{} frame #{}: {}x{}",
+            prepared.file_index, prepared.frame_index, img_width, img_height
+        );
+
         // 画像XObjectのID
         let image_id = Ref::new(*next_id);
         *next_id += 1;
-        
+
+        // アルファチャンネルがある場合はSMaskのIDを先に確保する
+        let smask_id = if encoded.smask.is_some() {
+            let id = Ref::new(*next_id);
+            *next_id += 1;
+            Some(id)
+        } else {
+            None
+        };
+
         // コンテンツストリームのID
         let content_id = Ref::new(*next_id);
         *next_id += 1;
-        
+
         // 画像XObjectを作成
-        let mut image_obj = pdf.image_xobject(image_id, &image_bytes);
-        image_obj.filter(Filter::DctDecode);
+        let (image_bytes, filter, bits_per_component) = match &encoded.payload {
+            ImagePayload::Jpeg { data, .. } => (data.as_slice(), Filter::DctDecode, 8),
+            ImagePayload::Flate { data, bits_per_component, .. } => {
+                (data.as_slice(), Filter::FlateDecode, *bits_per_component)
+            }
+        };
+
+        let mut image_obj = pdf.image_xobject(image_id, image_bytes);
+        image_obj.filter(filter);
         image_obj.width(img_width as i32);
         image_obj.height(img_height as i32);
-        image_obj.color_space().device_rgb();
-        image_obj.bits_per_component(8);
+        match &encoded.payload {
+            ImagePayload::Jpeg { color_space, .. } => match color_space {
+                JpegColorSpace::Gray => {
+                    image_obj.color_space().device_gray();
+                }
+                JpegColorSpace::Rgb => {
+                    image_obj.color_space().device_rgb();
+                }
+                JpegColorSpace::Cmyk => {
+                    image_obj.color_space().device_cmyk();
+                    // AdobeのCMYK/YCCK JPEGはほぼ例外なく反転規約のため、素直に読めるようDecodeで戻す
+                    image_obj.decode([1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+                }
+            },
+            ImagePayload::Flate { color_space, .. } => match color_space {
+                FlateColorSpace::Gray => {
+                    image_obj.color_space().device_gray();
+                }
+                FlateColorSpace::Rgb => {
+                    image_obj.color_space().device_rgb();
+                }
+            },
+        }
+        image_obj.bits_per_component(bits_per_component);
+        if let Some(smask_id) = smask_id {
+            image_obj.s_mask(smask_id);
+        }
         image_obj.finish();
-        
-        // コンテンツストリームを作成
+
+        // アルファチャンネルをDeviceGrayのSMask XObjectとして書き込む
+        if let (Some(smask_id), Some(alpha_bytes)) = (smask_id, encoded.smask.as_ref()) {
+            let mut smask_obj = pdf.image_xobject(smask_id, alpha_bytes);
+            smask_obj.filter(Filter::FlateDecode);
+            smask_obj.width(img_width as i32);
+            smask_obj.height(img_height as i32);
+            smask_obj.color_space().device_gray();
+            smask_obj.bits_per_component(8);
+            smask_obj.finish();
+        }
+
+        // コンテンツストリームを作成（画像を用紙の印刷可能領域に収め、中央に配置する）
         let content = format!(
-            "q\n{} 0 0 {} 0 0 cm\n/Im1 Do\nQ",
-            page_width, page_height
+            "q\n{} 0 0 {} {} {} cm\n/Im1 Do\nQ",
+            page_layout.image_width, page_layout.image_height, page_layout.offset_x, page_layout.offset_y
         );
         pdf.stream(content_id, content.as_bytes());
-        
+
         // ページを作成
         let mut page = pdf.page(page_id);
-        page.media_box(Rect::new(0.0, 0.0, page_width, page_height));
+        page.media_box(Rect::new(0.0, 0.0, page_layout.page_width, page_layout.page_height));
         page.contents(content_id);
         let mut resources = page.resources();
         let mut xobjects = resources.x_objects();
@@ -244,12 +688,218 @@ impl ImageToPdfConverter {
         xobjects.finish();
         resources.finish();
         page.finish();
-        
-        debug!("Added image as page: {}x{} ({}x{} points)", img_width, img_height, page_width, page_height);
-        
+
+        debug!(
+            "Added image as page: {}x{} (page {}x{} points, image {}x{} points at ({}, {}))",
+            img_width, img_height, page_layout.page_width, page_layout.page_height,
+            page_layout.image_width, page_layout.image_height, page_layout.offset_x, page_layout.offset_y
+        );
+
         Ok(())
     }
 
+    /// 画像をPDF埋め込み用のペイロードにエンコードする
+    ///
+    /// 埋め込みモードが`Lossy`の場合は常にJPEGへ再エンコードする。
+    /// それ以外の場合、元ファイルが既にJPEGならバイト列をそのまま使い（再エンコードなし）、
+    /// それ以外（PNGなど）はサンプルデータをzlib圧縮してFlateDecodeで埋め込む。
+    ///
+    /// # Arguments
+    /// * `file_path` - 元画像のファイルパス
+    /// * `img` - エンコード対象の画像
+    /// * `frame_count` - `file_path`がデコードされた総フレーム数（1より大きい場合はJPEG verbatim埋め込みを行わない）
+    ///
+    /// # Returns
+    /// * `Result<EncodedImage>` - エンコード結果
+    fn encode_image(&self, file_path: &Path, img: &DynamicImage, frame_count: usize) -> Result<EncodedImage> {
+        if self.options.embed_mode != EmbedMode::Lossy && frame_count == 1 && self.is_jpeg_source(file_path) {
+            let bytes = fs::read(file_path)?;
+            match Self::jpeg_color_space(&bytes) {
+                Some(color_space) => {
+                    debug!("Embedding JPEG source verbatim (no re-encode): {:?}", file_path);
+                    return Ok(EncodedImage {
+                        payload: ImagePayload::Jpeg { data: bytes, color_space },
+                        smask: None,
+                    });
+                }
+                None => {
+                    // SOFのコンポーネント数が判定できない（未対応のJPEG変種など）。
+                    // 誤った色空間で埋め込むより、デコード済みの画像から安全に再構築する
+                    warn!(
+                        "Could not determine JPEG color space for {:?}; re-encoding instead of embedding verbatim",
+                        file_path
+                    );
+                }
+            }
+        }
+
+        match self.options.embed_mode {
+            EmbedMode::Lossy => {
+                let bytes = self.image_to_jpeg_bytes(img)?;
+                let color_space = Self::jpeg_color_space(&bytes).ok_or_else(|| {
+                    AppError::PdfCreation(format!("Failed to determine JPEG color space for {:?}", file_path))
+                })?;
+                Ok(EncodedImage {
+                    payload: ImagePayload::Jpeg { data: bytes, color_space },
+                    smask: None,
+                })
+            }
+            EmbedMode::Auto | EmbedMode::Lossless => self.encode_image_flate(img),
+        }
+    }
+
+    /// JPEGバイト列のSOFセグメントからコンポーネント数を読み取り、対応する色空間を返す
+    ///
+    /// 1コンポーネントはグレースケール、3コンポーネントはRGB(YCbCr)、
+    /// 4コンポーネントはCMYK(YCCK)として扱う。SOFが見つからない、あるいは
+    /// 未対応のコンポーネント数の場合は`None`を返す。
+    fn jpeg_color_space(data: &[u8]) -> Option<JpegColorSpace> {
+        if data.len() < 4 || data[0] != 0xFF || data[1] != 0xD8 {
+            return None;
+        }
+
+        let mut pos = 2;
+        while pos + 1 < data.len() {
+            if data[pos] != 0xFF {
+                return None;
+            }
+            let marker = data[pos + 1];
+            pos += 2;
+
+            // マーカーセグメント長を持たないマーカー（SOI/EOI/RSTn/TEM）はスキップ
+            if marker == 0xD8 || marker == 0xD9 || marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+                continue;
+            }
+
+            if pos + 2 > data.len() {
+                return None;
+            }
+            let segment_len = u16::from_be_bytes([data[pos], data[pos + 1]]) as usize;
+
+            let is_sof = matches!(marker, 0xC0..=0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF);
+            if is_sof {
+                // セグメント: [長さ(2)][精度(1)][高さ(2)][幅(2)][コンポーネント数(1)]...
+                let components = *data.get(pos + 2 + 1 + 2 + 2)?;
+                return match components {
+                    1 => Some(JpegColorSpace::Gray),
+                    3 => Some(JpegColorSpace::Rgb),
+                    4 => Some(JpegColorSpace::Cmyk),
+                    _ => None,
+                };
+            }
+
+            if marker == 0xDA {
+                // Start of Scanに到達したがSOFが見つからなかった
+                return None;
+            }
+
+            pos += segment_len;
+        }
+
+        None
+    }
+
+    /// ファイル拡張子からJPEG由来の画像かどうかを判定する
+    fn is_jpeg_source(&self, file_path: &Path) -> bool {
+        file_path
+            .extension()
+            .map(|ext| {
+                let ext = ext.to_string_lossy().to_lowercase();
+                ext == "jpg" || ext == "jpeg"
+            })
+            .unwrap_or(false)
+    }
+
+    /// 画像の生サンプルをzlib圧縮し、FlateDecode用のペイロードにエンコードする
+    ///
+    /// 全画素でR==G==Bの場合はDeviceGray（輝度が0か255のみならビット深度1）とし、
+    /// それ以外は24bit DeviceRGBとする。アルファを持つ画像は、アルファチャンネルを
+    /// 別途8bit DeviceGrayのSMaskとして圧縮する。
+    fn encode_image_flate(&self, img: &DynamicImage) -> Result<EncodedImage> {
+        let rgba = img.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let has_alpha = img.color().has_alpha();
+
+        let mut is_grayscale = true;
+        let mut is_black_and_white = true;
+        for pixel in rgba.pixels() {
+            let [r, g, b, _] = pixel.0;
+            if r != g || g != b {
+                is_grayscale = false;
+                is_black_and_white = false;
+                break;
+            }
+            if r != 0 && r != 255 {
+                is_black_and_white = false;
+            }
+        }
+
+        let (payload_data, color_space, bits_per_component) = if is_grayscale && is_black_and_white
+        {
+            let packed = Self::pack_1bit_gray(&rgba, width, height);
+            (
+                Self::zlib_compress(&packed)?,
+                FlateColorSpace::Gray,
+                1,
+            )
+        } else if is_grayscale {
+            let gray: Vec<u8> = rgba.pixels().map(|p| p.0[0]).collect();
+            (Self::zlib_compress(&gray)?, FlateColorSpace::Gray, 8)
+        } else {
+            let rgb: Vec<u8> = rgba
+                .pixels()
+                .flat_map(|p| [p.0[0], p.0[1], p.0[2]])
+                .collect();
+            (Self::zlib_compress(&rgb)?, FlateColorSpace::Rgb, 8)
+        };
+
+        let smask = if has_alpha {
+            let alpha: Vec<u8> = rgba.pixels().map(|p| p.0[3]).collect();
+            Some(Self::zlib_compress(&alpha)?)
+        } else {
+            None
+        };
+
+        Ok(EncodedImage {
+            payload: ImagePayload::Flate {
+                data: payload_data,
+                color_space,
+                bits_per_component,
+            },
+            smask,
+        })
+    }
+
+    /// グレースケール画素を1ビット/画素のビットマップにパックする（行はバイト境界で揃える）
+    fn pack_1bit_gray(rgba: &image::RgbaImage, width: u32, height: u32) -> Vec<u8> {
+        let row_bytes = ((width as usize) + 7) / 8;
+        let mut packed = vec![0u8; row_bytes * height as usize];
+
+        for y in 0..height {
+            for x in 0..width {
+                let is_white = rgba.get_pixel(x, y).0[0] != 0;
+                if is_white {
+                    let byte_index = y as usize * row_bytes + (x as usize / 8);
+                    let bit_index = 7 - (x as usize % 8);
+                    packed[byte_index] |= 1 << bit_index;
+                }
+            }
+        }
+
+        packed
+    }
+
+    /// バイト列をzlib（RFC 1950）形式で圧縮する
+    fn zlib_compress(data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(data)
+            .map_err(|e| AppError::PdfCreation(format!("Failed to compress image data: {}", e)))?;
+        encoder
+            .finish()
+            .map_err(|e| AppError::PdfCreation(format!("Failed to finish compression: {}", e)))
+    }
+
     /// 画像をJPEGバイト配列に変換する
     ///
     /// # Arguments
@@ -260,10 +910,10 @@ impl ImageToPdfConverter {
     fn image_to_jpeg_bytes(&self, img: &DynamicImage) -> Result<Vec<u8>> {
         let mut buffer = Vec::new();
         let mut cursor = Cursor::new(&mut buffer);
-        
+
         // JPEG形式でエンコード（品質80%）
         img.write_to(&mut cursor, ImageOutputFormat::Jpeg(80))?;
-        
+
         Ok(buffer)
     }
 }
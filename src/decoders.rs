@@ -0,0 +1,341 @@
+use crate::error::{AppError, Result};
+use image::codecs::gif::GifDecoder as ImageGifDecoder;
+use image::{AnimationDecoder, DynamicImage};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// 画像デコーダーの共通インターフェース
+///
+/// 拡張子ごとにデコーダーを実装し、`DecoderRegistry`に登録することで
+/// 新しい画像形式をコンバーター本体に手を入れずに追加できる。
+pub trait ImageDecoder: Send + Sync {
+    /// このデコーダーが対応する拡張子一覧（小文字）
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// ファイルをデコードし、1枚以上のフレームを返す
+    ///
+    /// マルチページTIFFやアニメーションGIFのように複数フレームを持つ形式は、
+    /// フレームごとに1要素を返す。これがPDFの複数ページに展開される。
+    fn decode(&self, path: &Path) -> Result<Vec<DynamicImage>>;
+}
+
+/// `image`クレートが単一フレームとしてそのまま扱える形式（JPEG/PNG/WebP/BMP）
+struct RasterDecoder;
+
+impl ImageDecoder for RasterDecoder {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["jpg", "jpeg", "png", "webp", "bmp"]
+    }
+
+    fn decode(&self, path: &Path) -> Result<Vec<DynamicImage>> {
+        Ok(vec![image::open(path)?])
+    }
+}
+
+/// マルチページTIFFデコーダー（IFDごとに1フレーム）
+struct TiffDecoder;
+
+impl ImageDecoder for TiffDecoder {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["tif", "tiff"]
+    }
+
+    fn decode(&self, path: &Path) -> Result<Vec<DynamicImage>> {
+        let file = File::open(path)?;
+        let mut decoder = tiff::decoder::Decoder::new(BufReader::new(file))
+            .map_err(|e| AppError::PdfCreation(format!("Failed to open TIFF {:?}: {}", path, e)))?;
+
+        let mut frames = Vec::new();
+        loop {
+            let color_type = decoder
+                .colortype()
+                .map_err(|e| AppError::PdfCreation(format!("Failed to read TIFF color type in {:?}: {}", path, e)))?;
+            let color_map = if matches!(color_type, tiff::ColorType::Palette(_)) {
+                Some(
+                    decoder
+                        .get_tag_u16_vec(tiff::tags::Tag::ColorMap)
+                        .map_err(|e| AppError::PdfCreation(format!("Failed to read TIFF color map in {:?}: {}", path, e)))?,
+                )
+            } else {
+                None
+            };
+            let dimensions = decoder.dimensions().map_err(|e| {
+                AppError::PdfCreation(format!("Failed to read TIFF dimensions in {:?}: {}", path, e))
+            })?;
+            let image = decoder
+                .read_image()
+                .map_err(|e| AppError::PdfCreation(format!("Failed to decode TIFF frame in {:?}: {}", path, e)))?;
+            frames.push(tiff_result_to_dynamic_image(dimensions, color_type, color_map, image, path)?);
+
+            if !decoder.more_images() {
+                break;
+            }
+            decoder
+                .next_image()
+                .map_err(|e| AppError::PdfCreation(format!("Failed to seek next TIFF frame in {:?}: {}", path, e)))?;
+        }
+
+        Ok(frames)
+    }
+}
+
+/// `tiff`クレートのデコード結果を、実際の`ColorType`に基づいて`DynamicImage`へ変換する
+///
+/// バッファ長と`width * height`の比較だけではパレットカラー画像が誤ってグレースケールと
+/// 判定されてしまう（インデックス列がグレー濃度列とサイズ上区別できないため）ので、
+/// デコーダーが報告する`ColorType`で明示的に分岐し、パレット画像は`ColorMap`タグを使って
+/// RGBへ展開する。対応していない色形式は黙って推測せずエラーにする。
+fn tiff_result_to_dynamic_image(
+    (width, height): (u32, u32),
+    color_type: tiff::ColorType,
+    color_map: Option<Vec<u16>>,
+    image: tiff::decoder::DecodingResult,
+    path: &Path,
+) -> Result<DynamicImage> {
+    use tiff::decoder::DecodingResult;
+    use tiff::ColorType;
+
+    match (color_type, image) {
+        (ColorType::Gray(8), DecodingResult::U8(data)) => {
+            let buffer = image::GrayImage::from_raw(width, height, data)
+                .ok_or_else(|| AppError::PdfCreation("Invalid TIFF gray buffer".to_string()))?;
+            Ok(DynamicImage::ImageLuma8(buffer))
+        }
+        (ColorType::GrayA(8), DecodingResult::U8(data)) => {
+            let buffer = image::GrayAlphaImage::from_raw(width, height, data)
+                .ok_or_else(|| AppError::PdfCreation("Invalid TIFF gray+alpha buffer".to_string()))?;
+            Ok(DynamicImage::ImageLumaA8(buffer))
+        }
+        (ColorType::RGB(8), DecodingResult::U8(data)) => {
+            let buffer = image::RgbImage::from_raw(width, height, data)
+                .ok_or_else(|| AppError::PdfCreation("Invalid TIFF RGB buffer".to_string()))?;
+            Ok(DynamicImage::ImageRgb8(buffer))
+        }
+        (ColorType::RGBA(8), DecodingResult::U8(data)) => {
+            let buffer = image::RgbaImage::from_raw(width, height, data)
+                .ok_or_else(|| AppError::PdfCreation("Invalid TIFF RGBA buffer".to_string()))?;
+            Ok(DynamicImage::ImageRgba8(buffer))
+        }
+        (ColorType::Palette(8), DecodingResult::U8(indices)) => {
+            let color_map = color_map
+                .ok_or_else(|| AppError::PdfCreation(format!("TIFF palette image is missing a color map in {:?}", path)))?;
+            expand_palette(width, height, &indices, &color_map, path)
+        }
+        (other, _) => Err(AppError::PdfCreation(format!(
+            "Unsupported TIFF color type {:?} in {:?}",
+            other, path
+        ))),
+    }
+}
+
+/// パレットカラーTIFFのインデックス列を、`ColorMap`タグを使ってRGBへ展開する
+///
+/// TIFF仕様上、`ColorMap`は赤・緑・青それぞれのチャンネル値が連続して並んだ
+/// `3 * 2^BitsPerSample`要素の配列で、各値は0-65535にスケールされている。
+fn expand_palette(width: u32, height: u32, indices: &[u8], color_map: &[u16], path: &Path) -> Result<DynamicImage> {
+    if color_map.is_empty() || color_map.len() % 3 != 0 {
+        return Err(AppError::PdfCreation(format!("Invalid TIFF color map in {:?}", path)));
+    }
+    let channel_len = color_map.len() / 3;
+
+    let mut rgb = Vec::with_capacity(indices.len() * 3);
+    for &index in indices {
+        let i = index as usize;
+        if i >= channel_len {
+            return Err(AppError::PdfCreation(format!("TIFF palette index out of range in {:?}", path)));
+        }
+        // ColorMapの値は16bitスケールなので、上位バイトを取って8bitチャンネルにする
+        rgb.push((color_map[i] >> 8) as u8);
+        rgb.push((color_map[channel_len + i] >> 8) as u8);
+        rgb.push((color_map[2 * channel_len + i] >> 8) as u8);
+    }
+
+    let buffer = image::RgbImage::from_raw(width, height, rgb)
+        .ok_or_else(|| AppError::PdfCreation("Invalid TIFF palette buffer".to_string()))?;
+    Ok(DynamicImage::ImageRgb8(buffer))
+}
+
+/// アニメーションGIFデコーダー（フレームごとに1ページ）
+struct GifDecoder;
+
+impl ImageDecoder for GifDecoder {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["gif"]
+    }
+
+    fn decode(&self, path: &Path) -> Result<Vec<DynamicImage>> {
+        let file = File::open(path)?;
+        let decoder = ImageGifDecoder::new(BufReader::new(file))?;
+
+        let mut frames = Vec::new();
+        for frame in decoder.into_frames() {
+            let frame = frame?;
+            frames.push(DynamicImage::ImageRgba8(frame.into_buffer()));
+        }
+
+        if frames.is_empty() {
+            return Err(AppError::PdfCreation(format!("GIF contains no frames: {:?}", path)));
+        }
+
+        Ok(frames)
+    }
+}
+
+/// HEIF/AVIFデコーダー（主画像のみの1フレーム）
+struct HeifDecoder;
+
+impl ImageDecoder for HeifDecoder {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["heif", "heic", "avif"]
+    }
+
+    fn decode(&self, path: &Path) -> Result<Vec<DynamicImage>> {
+        let ctx = libheif_rs::HeifContext::read_from_file(&path.to_string_lossy())
+            .map_err(|e| AppError::PdfCreation(format!("Failed to open HEIF/AVIF {:?}: {}", path, e)))?;
+        let handle = ctx
+            .primary_image_handle()
+            .map_err(|e| AppError::PdfCreation(format!("Failed to read primary image in {:?}: {}", path, e)))?;
+        let image = handle
+            .decode(libheif_rs::ColorSpace::Rgb(libheif_rs::RgbChroma::Rgba), None)
+            .map_err(|e| AppError::PdfCreation(format!("Failed to decode HEIF/AVIF {:?}: {}", path, e)))?;
+
+        let width = handle.width();
+        let height = handle.height();
+        let plane = image
+            .planes()
+            .interleaved
+            .ok_or_else(|| AppError::PdfCreation("HEIF/AVIF image has no interleaved plane".to_string()))?;
+
+        // libheifはプレーンの各行をstride境界でパディングすることがあるため、
+        // width*4にタイトパックされている前提のfrom_rawへそのまま渡せない。
+        // 行ごとにstride分をスキップしながらコピーし、詰めたバッファを作る。
+        let row_bytes = width as usize * 4;
+        let mut packed = Vec::with_capacity(row_bytes * height as usize);
+        for row in 0..height as usize {
+            let start = row * plane.stride;
+            let row_data = plane
+                .data
+                .get(start..start + row_bytes)
+                .ok_or_else(|| AppError::PdfCreation("HEIF/AVIF plane data shorter than expected".to_string()))?;
+            packed.extend_from_slice(row_data);
+        }
+
+        let buffer = image::RgbaImage::from_raw(width, height, packed)
+            .ok_or_else(|| AppError::PdfCreation("Invalid HEIF/AVIF pixel buffer".to_string()))?;
+
+        Ok(vec![DynamicImage::ImageRgba8(buffer)])
+    }
+}
+
+/// SVGラスタライズ時の解像度設定
+#[derive(Debug, Clone, Copy)]
+pub struct SvgOptions {
+    /// ラスタライズ解像度（DPI）。`scale`が指定されない場合に使用される
+    pub dpi: f32,
+    /// ラスタライズ倍率。指定時は`dpi`より優先される
+    pub scale: Option<f32>,
+}
+
+impl SvgOptions {
+    /// CSSの基準解像度（96 DPI）を1倍として、設定値から実際の倍率を求める
+    fn effective_scale(&self) -> f32 {
+        self.scale.unwrap_or(self.dpi / 96.0)
+    }
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self { dpi: 150.0, scale: None }
+    }
+}
+
+/// SVGベクター画像デコーダー（usvg/resvgでラスタライズ）
+struct SvgDecoder {
+    options: SvgOptions,
+}
+
+impl ImageDecoder for SvgDecoder {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["svg"]
+    }
+
+    fn decode(&self, path: &Path) -> Result<Vec<DynamicImage>> {
+        let data = std::fs::read(path)?;
+        let svg_options = usvg::Options::default();
+        let tree = usvg::Tree::from_data(&data, &svg_options)
+            .map_err(|e| AppError::PdfCreation(format!("Failed to parse SVG {:?}: {}", path, e)))?;
+
+        // SVGには固有のピクセルサイズがないため、文書サイズ（`tree.size()`）にDPI/倍率を掛けて出力サイズを求める
+        let scale = self.options.effective_scale();
+        let svg_size = tree.size();
+        let width = (svg_size.width() * scale).round().max(1.0) as u32;
+        let height = (svg_size.height() * scale).round().max(1.0) as u32;
+
+        let mut pixmap = tiny_skia::Pixmap::new(width, height)
+            .ok_or_else(|| AppError::PdfCreation(format!("Invalid SVG output size for {:?}", path)))?;
+
+        resvg::render(
+            &tree,
+            tiny_skia::Transform::from_scale(scale, scale),
+            &mut pixmap.as_mut(),
+        );
+
+        let buffer = image::RgbaImage::from_raw(width, height, pixmap.take())
+            .ok_or_else(|| AppError::PdfCreation(format!("Invalid SVG pixel buffer for {:?}", path)))?;
+
+        Ok(vec![DynamicImage::ImageRgba8(buffer)])
+    }
+}
+
+/// 拡張子に応じたデコーダーを引き当てるレジストリ
+pub struct DecoderRegistry {
+    decoders: Vec<Box<dyn ImageDecoder>>,
+}
+
+impl DecoderRegistry {
+    /// 標準デコーダー一式を登録したレジストリを作成する
+    ///
+    /// # Arguments
+    /// * `svg_options` - SVGラスタライズ時の解像度設定
+    pub fn new(svg_options: SvgOptions) -> Self {
+        Self {
+            decoders: vec![
+                Box::new(RasterDecoder),
+                Box::new(TiffDecoder),
+                Box::new(GifDecoder),
+                Box::new(HeifDecoder),
+                Box::new(SvgDecoder { options: svg_options }),
+            ],
+        }
+    }
+
+    /// 登録されている全デコーダーの対応拡張子一覧
+    pub fn supported_extensions(&self) -> Vec<&'static str> {
+        self.decoders.iter().flat_map(|d| d.extensions().iter().copied()).collect()
+    }
+
+    /// 拡張子からデコーダーを選び、ファイルをデコードする
+    ///
+    /// # Arguments
+    /// * `path` - デコードするファイルのパス
+    /// * `extension` - 小文字化済みの拡張子
+    ///
+    /// # Returns
+    /// * `Result<Vec<DynamicImage>>` - デコードされたフレーム一覧
+    pub fn decode(&self, path: &Path, extension: &str) -> Result<Vec<DynamicImage>> {
+        let decoder = self
+            .decoders
+            .iter()
+            .find(|d| d.extensions().contains(&extension))
+            .ok_or_else(|| AppError::InvalidExtension(extension.to_string()))?;
+
+        decoder.decode(path)
+    }
+}
+
+impl Default for DecoderRegistry {
+    fn default() -> Self {
+        Self::new(SvgOptions::default())
+    }
+}
@@ -21,6 +21,9 @@ pub enum AppError {
     #[error("No valid image files found in directory: {0}")]
     NoImagesFound(String),
 
+    #[error("No pages could be produced from any image in directory: {0}")]
+    NoPagesProduced(String),
+
     #[error("Invalid file extension: {0}")]
     InvalidExtension(String),
 
@@ -0,0 +1,145 @@
+/// 1ミリメートルあたりのポイント数（PDFは1/72インチ単位）
+const POINTS_PER_MM: f32 = 72.0 / 25.4;
+
+/// 定型の用紙サイズ
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum PaperSize {
+    A4,
+    Letter,
+}
+
+impl PaperSize {
+    /// 用紙サイズをポイント単位の(幅, 高さ)で返す（ポートレート基準）
+    fn dimensions_pt(&self) -> (f32, f32) {
+        match self {
+            PaperSize::A4 => (210.0 * POINTS_PER_MM, 297.0 * POINTS_PER_MM),
+            PaperSize::Letter => (215.9 * POINTS_PER_MM, 279.4 * POINTS_PER_MM),
+        }
+    }
+}
+
+/// ページの用紙サイズ指定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PageSize {
+    /// A4やLetterなどの定型サイズ
+    Standard(PaperSize),
+    /// ミリメートル単位のカスタムサイズ（幅, 高さ）
+    Custom { width_mm: f32, height_mm: f32 },
+}
+
+impl PageSize {
+    /// 用紙サイズをポイント単位の(幅, 高さ)で返す（ポートレート基準）
+    fn dimensions_pt(&self) -> (f32, f32) {
+        match self {
+            PageSize::Standard(paper) => paper.dimensions_pt(),
+            PageSize::Custom { width_mm, height_mm } => {
+                (width_mm * POINTS_PER_MM, height_mm * POINTS_PER_MM)
+            }
+        }
+    }
+}
+
+/// ページの向き
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Orientation {
+    /// 画像のアスペクト比に合わせて自動で決定する
+    Auto,
+    Portrait,
+    Landscape,
+}
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Orientation::Auto
+    }
+}
+
+/// ページレイアウトを制御するオプション
+#[derive(Debug, Clone, Default)]
+pub struct LayoutOptions {
+    /// 固定の用紙サイズ。`None`の場合は従来通り画像サイズそのままのページにする
+    pub page_size: Option<PageSize>,
+    /// 画像の解像度（DPI）。ピクセル数をポイントへ変換するのに使う
+    pub dpi: Option<f32>,
+    /// 余白（ミリメートル）。`page_size`指定時のみ適用される
+    pub margin_mm: f32,
+    /// ページの向き
+    pub orientation: Orientation,
+}
+
+/// 計算済みのページレイアウト
+#[derive(Debug, Clone, Copy)]
+pub struct PageLayout {
+    /// ページ全体のサイズ（ポイント）
+    pub page_width: f32,
+    pub page_height: f32,
+    /// 画像を描画する幅・高さ（ポイント）
+    pub image_width: f32,
+    pub image_height: f32,
+    /// 画像の左下原点からのオフセット（ポイント）
+    pub offset_x: f32,
+    pub offset_y: f32,
+}
+
+/// 画像サイズとレイアウトオプションから、ページと画像配置を計算する
+///
+/// `page_size`が`None`の場合は画像サイズ＝ページサイズ（余白なし）のレイアウトを返す。
+/// ポイント換算には`dpi`（未指定時は72 DPI、従来動作と同じ）を使う。固定用紙が指定された場合は、
+/// アスペクト比を保ったまま印刷可能領域に収まるスケールを求めて中央に配置する。
+///
+/// # Arguments
+/// * `img_width_px` - 画像の幅（ピクセル）
+/// * `img_height_px` - 画像の高さ（ピクセル）
+/// * `options` - レイアウトオプション
+///
+/// # Returns
+/// * `PageLayout` - 計算済みのページレイアウト
+pub fn compute_layout(img_width_px: u32, img_height_px: u32, options: &LayoutOptions) -> PageLayout {
+    // ソースDPIからの画像の本来のサイズ（ポイント）
+    let dpi = options.dpi.unwrap_or(72.0);
+    let image_width_pt = img_width_px as f32 / dpi * 72.0;
+    let image_height_pt = img_height_px as f32 / dpi * 72.0;
+
+    let Some(page_size) = options.page_size else {
+        // 従来動作: ページ＝画像サイズ、オフセットなし
+        return PageLayout {
+            page_width: image_width_pt,
+            page_height: image_height_pt,
+            image_width: image_width_pt,
+            image_height: image_height_pt,
+            offset_x: 0.0,
+            offset_y: 0.0,
+        };
+    };
+
+    let (mut page_width, mut page_height) = page_size.dimensions_pt();
+
+    let is_image_portrait = img_height_px >= img_width_px;
+    let use_landscape = match options.orientation {
+        Orientation::Portrait => false,
+        Orientation::Landscape => true,
+        Orientation::Auto => !is_image_portrait,
+    };
+    if use_landscape {
+        std::mem::swap(&mut page_width, &mut page_height);
+    }
+
+    let margin_pt = options.margin_mm * POINTS_PER_MM;
+    let printable_width = (page_width - 2.0 * margin_pt).max(1.0);
+    let printable_height = (page_height - 2.0 * margin_pt).max(1.0);
+
+    // アスペクト比を保って印刷可能領域に収まる最大スケールを求める
+    let scale = (printable_width / image_width_pt).min(printable_height / image_height_pt);
+
+    let final_width = image_width_pt * scale;
+    let final_height = image_height_pt * scale;
+
+    PageLayout {
+        page_width,
+        page_height,
+        image_width: final_width,
+        image_height: final_height,
+        offset_x: (page_width - final_width) / 2.0,
+        offset_y: (page_height - final_height) / 2.0,
+    }
+}
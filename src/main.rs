@@ -3,11 +3,14 @@ use log::{error, info};
 use std::path::PathBuf;
 
 mod converter;
+mod decoders;
 mod error;
+mod layout;
 mod logger;
 
-use converter::ImageToPdfConverter;
+use converter::{ConverterOptions, EmbedMode, ImageToPdfConverter};
 use error::Result;
+use layout::{LayoutOptions, Orientation, PageSize, PaperSize};
 
 /// 複数の画像ファイルを1つのPDFファイルに変換するツール
 #[derive(Parser)]
@@ -31,6 +34,76 @@ struct Args {
     /// 詳細ログを有効にする
     #[arg(short, long)]
     verbose: bool,
+
+    /// 画像の埋め込み方式（auto: 自動判定、lossy: 常にJPEG、lossless: 可能な限り再エンコードなし）
+    #[arg(long, value_enum, default_value = "auto")]
+    mode: EmbedMode,
+
+    /// SVGをラスタライズする際の解像度（DPI）
+    #[arg(long, default_value_t = 150.0)]
+    svg_dpi: f32,
+
+    /// SVGをラスタライズする際の倍率（指定時は`--svg-dpi`より優先される）
+    #[arg(long)]
+    svg_scale: Option<f32>,
+
+    /// 固定の用紙サイズ（a4, letter、または"210x297"のようなミリメートル単位のWxH）。未指定時は従来通り画像サイズそのままのページになる
+    #[arg(long, value_parser = parse_page_size)]
+    page_size: Option<PageSize>,
+
+    /// 画像の解像度（DPI）。指定すると`pixels / dpi * 72`からページ/画像サイズを算出する
+    #[arg(long)]
+    dpi: Option<f32>,
+
+    /// 余白（ミリメートル）。`--page-size`指定時のみ有効
+    #[arg(long, default_value_t = 0.0)]
+    margin: f32,
+
+    /// ページの向き
+    #[arg(long, value_enum, default_value = "auto")]
+    orientation: Orientation,
+
+    /// 破損ファイルなどをスキップせず、最初のエラーで即座に失敗させる
+    #[arg(long)]
+    strict: bool,
+
+    /// スキップした画像のレポートを書き出すサイドカーファイル
+    #[arg(long)]
+    error_log: Option<PathBuf>,
+
+    /// サブディレクトリも再帰的に検索する
+    #[arg(long)]
+    recursive: bool,
+
+    /// 指定時は、入力ディレクトリ直下の各サブフォルダをフォルダ名のPDFとしてこのディレクトリに個別出力する
+    #[arg(long)]
+    outdir: Option<PathBuf>,
+
+    /// デコード/エンコードを並列実行する際の最大並列数（未指定時はCPUコア数に応じた既定値）
+    #[arg(short, long)]
+    jobs: Option<usize>,
+}
+
+/// `--page-size`の値をパースする（"a4" / "letter" / "<幅>x<高さ>"のミリメートル指定）
+fn parse_page_size(s: &str) -> std::result::Result<PageSize, String> {
+    match s.to_lowercase().as_str() {
+        "a4" => Ok(PageSize::Standard(PaperSize::A4)),
+        "letter" => Ok(PageSize::Standard(PaperSize::Letter)),
+        custom => {
+            let (width_str, height_str) = custom
+                .split_once('x')
+                .ok_or_else(|| format!("invalid page size: {} (expected a4, letter, or WxH in mm)", s))?;
+            let width_mm: f32 = width_str
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid page width: {}", width_str))?;
+            let height_mm: f32 = height_str
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid page height: {}", height_str))?;
+            Ok(PageSize::Custom { width_mm, height_mm })
+        }
+    }
 }
 
 fn main() -> Result<()> {
@@ -64,10 +137,33 @@ fn main() -> Result<()> {
     info!("Output file: {:?}", output_file);
 
     // 変換処理の実行
-    let converter = ImageToPdfConverter::new();
-    match converter.convert(&input_dir, &output_file) {
+    let options = ConverterOptions {
+        embed_mode: args.mode,
+        svg_dpi: args.svg_dpi,
+        svg_scale: args.svg_scale,
+        layout: LayoutOptions {
+            page_size: args.page_size,
+            dpi: args.dpi,
+            margin_mm: args.margin,
+            orientation: args.orientation,
+        },
+        strict: args.strict,
+        error_log: args.error_log,
+        recursive: args.recursive,
+        jobs: args.jobs,
+    };
+    let converter = ImageToPdfConverter::with_options(options);
+
+    let result = if let Some(outdir) = &args.outdir {
+        info!("Bulk mode: one PDF per subfolder under {:?} -> {:?}", input_dir, outdir);
+        converter.convert_bulk(&input_dir, outdir)
+    } else {
+        converter.convert(&input_dir, &output_file)
+    };
+
+    match result {
         Ok(_) => {
-            info!("PDF conversion completed successfully: {:?}", output_file);
+            info!("PDF conversion completed successfully");
         }
         Err(e) => {
             error!("PDF conversion failed: {}", e);